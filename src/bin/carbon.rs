@@ -28,7 +28,8 @@ Options:
   --bind HOST                 host to bind to [default: 0.0.0.0:2003]
   --chan DEPTH                how many carbon messages can be in-flight [default: 1000]
   --storage-path STORAGEPATH  where to find the whisper file [default: /tmp]
-  --cache-size CACHESIZE      max number of open files to keep in memory [default: 60000]
+  --cache-size CACHESIZE      max number of open files to keep in memory, defaults to
+                              a value below the soft `ulimit -n`
 ";
 
 #[derive(RustcDecodable, Debug)]
@@ -36,7 +37,7 @@ struct Args {
     flag_bind: String,
     flag_chan: usize,
     flag_storage_path: String,
-    flag_cache_size: usize
+    flag_cache_size: Option<usize>
 }
 
 pub fn main(){
@@ -45,15 +46,11 @@ pub fn main(){
                             .and_then(|d| d.decode())
                             .unwrap_or_else(|e| e.exit());
 
-    let bind_spec = unsafe {
-        args.flag_bind.slice_unchecked(0, args.flag_bind.len())
-    };
-
     let config = carbon::Config{
-        bind_spec: bind_spec,
+        bind_spec: &args.flag_bind,
         chan_depth: args.flag_chan,
         base_path: Path::new(&args.flag_storage_path),
-        cache_size: args.flag_cache_size
+        cache_size: args.flag_cache_size.unwrap_or_else(carbon::default_cache_size)
     };
 
     info!("preparing whisper cache...");
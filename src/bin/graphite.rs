@@ -42,12 +42,8 @@ pub fn main(){
                             .and_then(|d| d.decode())
                             .unwrap_or_else(|e| e.exit());
 
-    let bind_spec : &str = unsafe {
-        args.flag_bind.slice_unchecked(0, args.flag_bind.len())
-    };
-
     let config = Config{
-        bind_spec: bind_spec,
+        bind_spec: &args.flag_bind,
         base_path: Path::new(&args.flag_storage_path)
     };
 
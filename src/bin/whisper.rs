@@ -54,10 +54,7 @@ pub fn main(){
                             .unwrap_or_else(|e| e.exit());
 
     let arg_file = args.arg_file.clone();
-    let path_str : &str = unsafe {
-        arg_file.slice_unchecked(0, args.arg_file.len())
-    };
-    let path = Path::new(path_str);
+    let path = Path::new(&arg_file);
 
 
     let current_time = time::get_time().sec as u64;
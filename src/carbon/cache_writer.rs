@@ -5,6 +5,7 @@ use std::thread::{ self, JoinHandle };
 use std::sync::mpsc::{ sync_channel, SyncSender };
 
 use super::Config;
+use super::stats;
 use super::handlers::Action;
 
 pub fn spawn(cache: WhisperCache, config: &Config) -> (SyncSender<Action>, JoinHandle<()>) {
@@ -23,8 +24,11 @@ pub fn spawn(cache: WhisperCache, config: &Config) -> (SyncSender<Action>, JoinH
                     let write_res = cache.write( named_point );
 
                     match write_res {
-                        Ok(()) => (),
-                        Err(reason) => debug!("err: {:?}", reason)
+                        Ok(()) => stats::incr(&stats::POINTS_WRITTEN),
+                        Err(reason) => {
+                            stats::incr(&stats::WRITE_ERRORS);
+                            debug!("err: {:?}", reason)
+                        }
                     }
                 },
                 Err(_) => {
@@ -37,3 +41,93 @@ pub fn spawn(cache: WhisperCache, config: &Config) -> (SyncSender<Action>, JoinH
 
     (tx,writer)
 }
+
+#[cfg(test)]
+mod tests {
+    use whisper::{ WhisperCache, Schema };
+    use time;
+
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::mpsc::SyncSender;
+    use std::thread::JoinHandle;
+
+    use super::spawn;
+    use super::super::Config;
+    use super::super::handlers::{ Action, parse_action };
+    #[cfg(feature = "stats")]
+    use super::super::stats;
+
+    // Storage dir unique to one test in one run, removed on drop
+    struct TempStorage(PathBuf);
+
+    impl TempStorage {
+        fn new(name: &str) -> TempStorage {
+            let dir_name = format!("graphite-rust-{}-{}", name, process::id());
+            let path = env::temp_dir().join(dir_name);
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempStorage(path)
+        }
+    }
+
+    impl Drop for TempStorage {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn spawn_writer(storage: &TempStorage, cache_size: usize) -> (SyncSender<Action>, JoinHandle<()>) {
+        let config = Config{
+            bind_spec: "127.0.0.1:0",
+            chan_depth: 10,
+            base_path: &storage.0,
+            cache_size
+        };
+        let schema = Schema::new_from_retention_specs(vec!["1s:1h".to_string()]);
+        let cache = WhisperCache::new(&storage.0, config.cache_size, schema);
+
+        spawn(cache, &config)
+    }
+
+    #[test]
+    fn tiny_cache_evicts_and_keeps_every_file(){
+        let storage = TempStorage::new("cache-writer-evict");
+        let (tx, writer) = spawn_writer(&storage, 1);
+
+        let now = time::get_time().sec;
+        for metric in &["evict.one", "evict.two", "evict.three"] {
+            let line = format!("{} 4 {}", metric, now);
+            tx.send(parse_action(&line).unwrap()).unwrap();
+        }
+        drop(tx);
+        writer.join().unwrap();
+
+        // WhisperCache lays `a.b` out as `a/b.wsp`
+        for file in &["one.wsp", "two.wsp", "three.wsp"] {
+            let path = storage.0.join("evict").join(file);
+            let metadata = fs::metadata(&path).unwrap();
+            assert!(metadata.len() > 0, "{:?} is empty", path);
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn counts_points_through_the_writer(){
+        let storage = TempStorage::new("cache-writer-stats");
+
+        let before = stats::snapshot();
+        let (tx, writer) = spawn_writer(&storage, 10);
+
+        let line = format!("stats.test.written 4 {}", time::get_time().sec);
+        tx.send(parse_action(&line).unwrap()).unwrap();
+        drop(tx);
+        writer.join().unwrap();
+
+        let after = stats::snapshot();
+        assert!(after.points_received > before.points_received);
+        assert!(after.points_written > before.points_written);
+    }
+}
@@ -1,8 +1,78 @@
 use std::path::Path;
 
+use libc;
+
 pub struct Config<'a> {
     pub bind_spec: &'a str,
     pub chan_depth: usize,
     pub base_path: &'a Path,
     pub cache_size: usize
 }
+
+// Used when the fd limit can't be read or is unlimited.
+const FALLBACK_CACHE_SIZE : usize = 60000;
+
+// Descriptors kept free for the listening sockets, TCP clients, stdio
+// and whatever the cache writer is in the middle of opening.
+const RESERVED_FDS : u64 = 64;
+
+// Pick a cache size that stays under the soft `RLIMIT_NOFILE` so
+// the writer doesn't start failing with EMFILE on boxes with a low `ulimit -n`.
+pub fn default_cache_size() -> usize {
+    let mut rlim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    let res = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlim) };
+
+    if res != 0 {
+        warn!("could not read RLIMIT_NOFILE, using cache size of {}", FALLBACK_CACHE_SIZE);
+        return FALLBACK_CACHE_SIZE
+    }
+
+    if rlim.rlim_cur == libc::RLIM_INFINITY {
+        return FALLBACK_CACHE_SIZE
+    }
+
+    // rlim_t is only 32 bits on some targets
+    #[allow(clippy::useless_conversion)]
+    let soft_limit = u64::from(rlim.rlim_cur);
+    cache_size_for_limit(soft_limit)
+}
+
+fn cache_size_for_limit(soft_limit: u64) -> usize {
+    let size = if soft_limit > RESERVED_FDS * 2 {
+        soft_limit - RESERVED_FDS
+    } else {
+        soft_limit / 2
+    };
+
+    if size > FALLBACK_CACHE_SIZE as u64 {
+        FALLBACK_CACHE_SIZE
+    } else if size == 0 {
+        // WhisperCache needs room for at least the file being written
+        1
+    } else {
+        size as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ cache_size_for_limit, FALLBACK_CACHE_SIZE };
+
+    #[test]
+    fn leaves_headroom_under_common_limits(){
+        assert_eq!(cache_size_for_limit(1024), 960);
+        assert_eq!(cache_size_for_limit(4096), 4032);
+    }
+
+    #[test]
+    fn tiny_limits_get_half(){
+        assert_eq!(cache_size_for_limit(100), 50);
+        assert_eq!(cache_size_for_limit(1), 1);
+        assert_eq!(cache_size_for_limit(0), 1);
+    }
+
+    #[test]
+    fn huge_limits_are_capped(){
+        assert_eq!(cache_size_for_limit(1048576), FALLBACK_CACHE_SIZE);
+    }
+}
@@ -1,5 +1,8 @@
 use whisper::NamedPoint;
 
+use super::line;
+use super::stats;
+
 pub mod udp;
 pub mod tcp;
 
@@ -9,3 +12,52 @@ pub mod tcp;
 pub enum Action {
     Write(NamedPoint)
 }
+
+// Turn one protocol line into a write. `line::check_line` runs first so
+// unsafe metric names and nan/inf values never reach the cache.
+pub fn parse_action(raw_line: &str) -> Option<Action> {
+    let raw_line = raw_line.trim();
+
+    if let Err(err) = line::check_line(raw_line) {
+        stats::incr(&stats::PARSE_ERRORS);
+        error!("could not parse incoming data: {:?}", err);
+        return None
+    }
+
+    match NamedPoint::parse_line(raw_line) {
+        Ok(named_point) => {
+            stats::incr(&stats::POINTS_RECEIVED);
+            Some(Action::Write(named_point))
+        },
+        Err(err) => {
+            stats::incr(&stats::PARSE_ERRORS);
+            error!("could not parse incoming data: {:?}", err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_action;
+    #[cfg(feature = "stats")]
+    use super::super::stats;
+
+    #[test]
+    fn rejects_unsafe_and_non_finite_lines(){
+        assert!(parse_action("a.b nan 10").is_none());
+        assert!(parse_action("a./etc/evil 1 10").is_none());
+        assert!(parse_action("a.b 1 10").is_some());
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn counts_each_rejected_line(){
+        let before = stats::snapshot().parse_errors;
+
+        assert!(parse_action("a.b nan 10").is_none());
+        assert!(parse_action("a./etc/evil 1 10").is_none());
+
+        assert!(stats::snapshot().parse_errors >= before + 2);
+    }
+}
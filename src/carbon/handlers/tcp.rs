@@ -1,5 +1,3 @@
-use whisper::{ NamedPoint };
-
 use std::net::{ TcpListener, TcpStream };
 use std::io::{ Error, BufReader, BufRead };
 extern crate time;
@@ -8,7 +6,7 @@ use std::sync::mpsc::{ sync_channel, SyncSender };
 use std::thread::{ self, JoinHandle };
 
 use super::super::Config;
-use super::Action;
+use super::{ Action, parse_action };
 
 pub fn run_server(tx: SyncSender<Action>, config: &Config) -> Result<JoinHandle<Result<(),Error>>,Error> {
     info!("TCP server binding to `{}`", config.bind_spec);
@@ -39,8 +37,11 @@ pub fn run_server(tx: SyncSender<Action>, config: &Config) -> Result<JoinHandle<
 }
 
 fn do_server(tx: SyncSender<Action>, tcp_stream: TcpStream) {
+    read_lines(tx, BufReader::new(tcp_stream));
+}
+
+fn read_lines<R: BufRead>(tx: SyncSender<Action>, mut reader: R) {
     let mut line_buf = String::new();
-    let mut reader = BufReader::new(tcp_stream);
 
     loop {
         match reader.read_line(&mut line_buf) {
@@ -51,13 +52,9 @@ fn do_server(tx: SyncSender<Action>, tcp_stream: TcpStream) {
                 }
 
                 debug!("tcp listener read {} bytes", bytes_read);
-                let parsed_line = NamedPoint::parse_line(&(line_buf.trim_right())[..]);
-                match parsed_line {
-                    Ok(np) => tx.send(Action::Write(np)).unwrap(),
-                    Err(err) => {
-                        error!("could not parse incoming data: {:?}", err);
-                        break;
-                    }
+                // Bad lines are skipped, the rest of the connection still gets written
+                if let Some(action) = parse_action(&line_buf) {
+                    tx.send(action).unwrap();
                 }
 
                 line_buf.clear();
@@ -69,3 +66,21 @@ fn do_server(tx: SyncSender<Action>, tcp_stream: TcpStream) {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::mpsc::sync_channel;
+
+    use super::read_lines;
+
+    #[test]
+    fn bad_line_does_not_drop_the_connection(){
+        let (tx, rx) = sync_channel(10);
+        let input = Cursor::new("a..b 1 10\nlocal.random.diceroll nan 10\nlocal.random.diceroll 4 10\n");
+
+        read_lines(tx, input);
+
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+}
@@ -1,12 +1,12 @@
-use whisper::{ NamedPoint };
-
 use std::thread::{ self, JoinHandle };
 use std::net::UdpSocket;
 use std::io::Error;
 use std::sync::mpsc::{ SyncSender };
+use std::str;
 
 use super::super::Config;
-use super::Action;
+use super::super::stats;
+use super::{ Action, parse_action };
 
 pub fn run_server<'a>(tx: SyncSender<Action>, config: &Config) -> Result<JoinHandle<()>,Error> {
     info!("UDP server binding to `{}`", config.bind_spec);
@@ -31,18 +31,22 @@ pub fn run_server<'a>(tx: SyncSender<Action>, config: &Config) -> Result<JoinHan
 
             debug!("parsing point...");
 
-            match NamedPoint::from_datagram(&buf_box[0..bytes_read]) {
-                Ok(named_points) => {
-                    // Dies if the receiver is closed
-                    debug!("putting message on tx");
-                    for named_point in named_points {
-                        tx.send(Action::Write(named_point)).unwrap();
-                    }
-                },
+            let datagram = match str::from_utf8(&buf_box[0..bytes_read]) {
+                Ok(datagram) => datagram,
                 Err(err) => {
+                    stats::incr(&stats::PARSE_ERRORS);
                     debug!("wtf mate: {:?}", err);
+                    continue;
                 }
             };
+
+            // Bad lines are skipped, the rest of the datagram still gets written
+            for line in datagram.lines().filter(|l| !l.trim().is_empty()) {
+                if let Some(action) = parse_action(line) {
+                    // Dies if the receiver is closed
+                    tx.send(action).unwrap();
+                }
+            }
         }
     });
     Ok(join_handle)
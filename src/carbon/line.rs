@@ -0,0 +1,104 @@
+/*
+
+Checks for the carbon plaintext protocol, one point per line:
+
+    metric.path value timestamp
+
+NamedPoint does the actual parsing. Metric names come straight off the
+network and turn into file paths, so anything that could step outside
+the storage directory is rejected first, along with the nan/inf values
+carbon refuses.
+
+*/
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Metric(String),
+    Value(String)
+}
+
+// The metric is everything up to the first space, so a tab can't hide
+// a path inside what NamedPoint will treat as the name. Values that
+// don't parse at all are left for NamedPoint to reject.
+pub fn check_line(line: &str) -> Result<(), ParseError> {
+    let mut fields = line.splitn(2, ' ');
+    check_metric(fields.next().unwrap_or(""))?;
+
+    let value = fields.next().and_then(|rest| rest.split_whitespace().next());
+    if let Some(value) = value {
+        if let Ok(parsed) = value.parse::<f64>() {
+            if !parsed.is_finite() {
+                return Err(ParseError::Value(value.to_string()))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Rejects empty names, `/`, whitespace and control characters anywhere,
+// and empty, `.` or `..` parts
+pub fn check_metric(metric: &str) -> Result<(), ParseError> {
+    let bad_char = metric.chars().any(|c| c == '/' || c.is_whitespace() || c.is_control());
+    if metric.is_empty() || bad_char {
+        return Err(ParseError::Metric(metric.to_string()))
+    }
+
+    // `..` also shows up here as an empty part
+    if metric.split('.').any(|part| part.is_empty()) {
+        return Err(ParseError::Metric(metric.to_string()))
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ check_line, check_metric, ParseError };
+
+    fn metric_err(metric: &str) -> ParseError {
+        ParseError::Metric(metric.to_string())
+    }
+
+    #[test]
+    fn accepts_valid_lines(){
+        assert_eq!(check_line("local.random.diceroll 4 1446000000"), Ok(()));
+        assert_eq!(check_line("temp.outside -12.25 1446000000"), Ok(()));
+    }
+
+    #[test]
+    fn accepts_multibyte_metrics(){
+        assert_eq!(check_metric("sërvers.wëb1.负载"), Ok(()));
+    }
+
+    #[test]
+    fn leaves_unparseable_values_to_named_point(){
+        assert_eq!(check_line("a.b four 10"), Ok(()));
+        assert_eq!(check_line("a.b"), Ok(()));
+    }
+
+    #[test]
+    fn rejects_non_finite_values(){
+        for value in &["nan", "NaN", "inf", "-inf", "infinity"] {
+            let line = format!("a.b {} 10", value);
+            assert_eq!(check_line(&line), Err(ParseError::Value(value.to_string())));
+        }
+    }
+
+    #[test]
+    fn rejects_metrics_outside_base(){
+        let bad = [
+            "", "a./etc/evil", "/etc/cron", "a/../../../root/x", "a\0b", "a\tb",
+            "a..b", "a.b.", ".a", ".", "..", "a...b"
+        ];
+
+        for metric in &bad {
+            assert_eq!(check_metric(metric), Err(metric_err(metric)));
+        }
+    }
+
+    #[test]
+    fn tab_cannot_hide_a_path_in_the_metric(){
+        assert_eq!(check_line("a\t/etc/evil 1 10"), Err(metric_err("a\t/etc/evil")));
+    }
+}
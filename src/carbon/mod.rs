@@ -7,7 +7,10 @@ for
 
 mod handlers;
 pub mod cache_writer;
+pub mod line;
+pub mod schemas;
+pub mod stats;
 mod config;
 
 pub use self::handlers::{ tcp, udp };
-pub use self::config::Config;
+pub use self::config::{ Config, default_cache_size };
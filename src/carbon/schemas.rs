@@ -0,0 +1,368 @@
+/*
+
+Schema selection in the style of carbon's `storage-schemas.conf`:
+
+    [carbon]
+    pattern = ^carbon\.
+    retentions = 60s:90d
+    priority = 10
+
+    [everything_else]
+    pattern = .*
+    retentions = 5s:1d,1m:1y
+
+The highest `priority` whose pattern matches the metric name wins. `priority`
+is optional and defaults to 0; between equal priorities the rule that comes
+first in the file wins, like carbon. Metrics matching no rule get the default
+retentions.
+
+*/
+
+use whisper::Schema;
+use regex::Regex;
+
+use std::cmp::Reverse;
+
+#[derive(Debug, PartialEq)]
+pub enum ConfError {
+    // line number and the offending line
+    Syntax(usize, String),
+    // section name and the missing key
+    MissingKey(String, &'static str),
+    // section name and why
+    BadPattern(String, String),
+    BadPriority(String, String),
+    // section name and the retention spec
+    BadRetention(String, String)
+}
+
+// The single-letter units whisper's `Schema::new_from_retention_specs` understands
+pub const UNITS : [&str; 6] = ["s", "m", "h", "d", "w", "y"];
+
+pub struct SchemaRule {
+    pub name: String,
+    pub pattern: Regex,
+    pub priority: i64,
+    pub retentions: Vec<String>
+}
+
+pub struct StorageSchemas {
+    rules: Vec<SchemaRule>,
+    default_retentions: Vec<String>
+}
+
+impl StorageSchemas {
+    pub fn new(rules: Vec<SchemaRule>, default_retentions: Vec<String>) -> StorageSchemas {
+        let mut rules = rules;
+        // stable, so file order breaks ties
+        rules.sort_by_key(|rule| Reverse(rule.priority));
+
+        StorageSchemas {
+            rules,
+            default_retentions
+        }
+    }
+
+    pub fn parse(conf: &str, default_retentions: Vec<String>) -> Result<StorageSchemas, ConfError> {
+        let mut rules = vec![];
+        let mut section : Option<Section> = None;
+
+        for (index, raw_line) in conf.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with("#") || line.starts_with(";") {
+                continue;
+            }
+
+            if line.starts_with("[") && line.ends_with("]") {
+                if let Some(done) = section.take() {
+                    rules.push(done.into_rule()?);
+                }
+                section = Some(Section::new(&line[1..line.len()-1]));
+                continue;
+            }
+
+            let current = match section {
+                Some(ref mut current) => current,
+                None => return Err(ConfError::Syntax(index+1, raw_line.to_string()))
+            };
+
+            let mut key_value = line.splitn(2, '=');
+            let key = key_value.next().unwrap().trim();
+            let value = match key_value.next() {
+                Some(value) => value.trim(),
+                None => return Err(ConfError::Syntax(index+1, raw_line.to_string()))
+            };
+
+            match key {
+                "pattern" => current.pattern = Some(value.to_string()),
+                "retentions" => current.retentions = Some(value.to_string()),
+                "priority" => current.priority = Some(value.to_string()),
+                _ => debug!("ignoring `{}` in storage schema [{}]", key, current.name)
+            }
+        }
+
+        if let Some(done) = section.take() {
+            rules.push(done.into_rule()?);
+        }
+
+        Ok(StorageSchemas::new(rules, default_retentions))
+    }
+
+    pub fn retentions_for(&self, metric: &str) -> &[String] {
+        for rule in self.rules.iter() {
+            if rule.pattern.is_match(metric) {
+                return &rule.retentions[..]
+            }
+        }
+        &self.default_retentions[..]
+    }
+
+    pub fn schema_for(&self, metric: &str) -> Schema {
+        Schema::new_from_retention_specs(self.retentions_for(metric).to_vec())
+    }
+}
+
+struct Section {
+    name: String,
+    pattern: Option<String>,
+    retentions: Option<String>,
+    priority: Option<String>
+}
+
+impl Section {
+    fn new(name: &str) -> Section {
+        Section {
+            name: name.trim().to_string(),
+            pattern: None,
+            retentions: None,
+            priority: None
+        }
+    }
+
+    fn into_rule(self) -> Result<SchemaRule, ConfError> {
+        let name = self.name;
+
+        let pattern_str = match self.pattern {
+            Some(pattern_str) => pattern_str,
+            None => return Err(ConfError::MissingKey(name, "pattern"))
+        };
+        let pattern = match Regex::new(&pattern_str) {
+            Ok(pattern) => pattern,
+            Err(err) => return Err(ConfError::BadPattern(name, format!("{}", err)))
+        };
+
+        let retentions : Vec<String> = match self.retentions {
+            Some(retentions) => retentions.split(',')
+                                          .map(|r| r.trim().to_string())
+                                          .filter(|r| !r.is_empty())
+                                          .collect(),
+            None => vec![]
+        };
+        if retentions.is_empty() {
+            return Err(ConfError::MissingKey(name, "retentions"))
+        }
+        // Caught here rather than in schema_for, which runs on the writer thread
+        if let Some(bad) = retentions.iter().find(|r| !valid_retention(r)) {
+            return Err(ConfError::BadRetention(name, bad.clone()))
+        }
+
+        let priority = match self.priority {
+            Some(priority_str) => match priority_str.parse::<i64>() {
+                Ok(priority) => priority,
+                Err(_) => return Err(ConfError::BadPriority(name, priority_str))
+            },
+            None => 0
+        };
+
+        Ok(SchemaRule {
+            name,
+            pattern,
+            priority,
+            retentions
+        })
+    }
+}
+
+// `precision:retention`, each side a positive count and one of `UNITS`,
+// e.g. `10s:1d`. Longer unit names and bare counts aren't supported by
+// the whisper crate's parser yet, so they are rejected here too.
+fn valid_retention(spec: &str) -> bool {
+    let mut parts = spec.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(precision), Some(retention), None) => {
+            valid_retention_part(precision) && valid_retention_part(retention)
+        },
+        _ => false
+    }
+}
+
+fn valid_retention_part(part: &str) -> bool {
+    let digits = part.chars().take_while(|c| c.is_ascii_digit()).count();
+    let (count, unit) = part.split_at(digits);
+
+    match count.parse::<u64>() {
+        Ok(count) if count > 0 => (),
+        _ => return false
+    }
+
+    UNITS.contains(&unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ StorageSchemas, ConfError, UNITS };
+
+    fn default_retentions() -> Vec<String> {
+        vec!["5s:1y".to_string()]
+    }
+
+    #[test]
+    fn priority_picks_winner_of_overlapping_patterns(){
+        let conf = "
+            # catch-all listed first on purpose
+            [everything]
+            pattern = .*
+            retentions = 1m:1y
+
+            [carbon]
+            pattern = ^carbon\\.
+            retentions = 60s:90d, 1h:1y
+            priority = 10
+        ";
+        let schemas = StorageSchemas::parse(conf, default_retentions()).unwrap();
+
+        assert_eq!(schemas.retentions_for("carbon.agents.cpu"), &["60s:90d".to_string(), "1h:1y".to_string()][..]);
+        assert_eq!(schemas.retentions_for("servers.web1.cpu"), &["1m:1y".to_string()][..]);
+    }
+
+    #[test]
+    fn file_order_breaks_ties(){
+        let conf = "
+            [first]
+            pattern = ^servers\\.
+            retentions = 10s:1d
+
+            [second]
+            pattern = ^servers\\.web
+            retentions = 1m:1d
+        ";
+        let schemas = StorageSchemas::parse(conf, default_retentions()).unwrap();
+
+        assert_eq!(schemas.retentions_for("servers.web1.cpu"), &["10s:1d".to_string()][..]);
+    }
+
+    #[test]
+    fn no_match_falls_back_to_default(){
+        let conf = "
+            [carbon]
+            pattern = ^carbon\\.
+            retentions = 60s:90d
+        ";
+        let schemas = StorageSchemas::parse(conf, default_retentions()).unwrap();
+
+        assert_eq!(schemas.retentions_for("local.random.diceroll"), &default_retentions()[..]);
+    }
+
+    #[test]
+    fn missing_retentions_is_an_error(){
+        let conf = "
+            [carbon]
+            pattern = ^carbon\\.
+        ";
+        match StorageSchemas::parse(conf, default_retentions()) {
+            Err(err) => assert_eq!(err, ConfError::MissingKey("carbon".to_string(), "retentions")),
+            Ok(_) => panic!("expected a missing key error")
+        }
+    }
+
+    #[test]
+    fn key_outside_section_is_an_error(){
+        match StorageSchemas::parse("pattern = .*", default_retentions()) {
+            Err(err) => assert_eq!(err, ConfError::Syntax(1, "pattern = .*".to_string())),
+            Ok(_) => panic!("expected a syntax error")
+        }
+    }
+
+    #[test]
+    fn bad_retention_is_an_error(){
+        let conf = "
+            [carbon]
+            pattern = ^carbon\\.
+            retentions = 60s:1d, 60s:9Od
+        ";
+        match StorageSchemas::parse(conf, default_retentions()) {
+            Err(err) => assert_eq!(err, ConfError::BadRetention("carbon".to_string(), "60s:9Od".to_string())),
+            Ok(_) => panic!("expected a bad retention error")
+        }
+    }
+
+    #[test]
+    fn every_accepted_form_builds_a_schema(){
+        for precision_unit in &UNITS {
+            for retention_unit in &UNITS {
+                let spec = format!("10{}:2{}", precision_unit, retention_unit);
+                let conf = format!("[all]\npattern = .*\nretentions = {}\n", spec);
+                let schemas = StorageSchemas::parse(&conf, default_retentions()).unwrap();
+
+                assert_eq!(schemas.schema_for("any.metric").retention_policies.len(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_forms_whisper_cant_parse(){
+        let bad = [
+            "0s:1d", "1d", "1d:2d:3d", "s:1d", "1x:1d", "-1s:1d",
+            "10:60", "1min:7d", "1m:7days", "1D:1y", "1s:"
+        ];
+
+        for bad in &bad {
+            let conf = format!("[all]\npattern = .*\nretentions = {}\n", bad);
+            match StorageSchemas::parse(&conf, default_retentions()) {
+                Err(err) => assert_eq!(err, ConfError::BadRetention("all".to_string(), bad.to_string())),
+                Ok(_) => panic!("expected {} to be rejected", bad)
+            }
+        }
+    }
+
+    #[test]
+    fn bad_pattern_is_an_error(){
+        let conf = "
+            [carbon]
+            pattern = ^carbon(
+            retentions = 60s:1d
+        ";
+        match StorageSchemas::parse(conf, default_retentions()) {
+            Err(ConfError::BadPattern(name, _)) => assert_eq!(name, "carbon"),
+            Err(err) => panic!("expected a bad pattern error, got {:?}", err),
+            Ok(_) => panic!("expected a bad pattern error")
+        }
+    }
+
+    #[test]
+    fn bad_priority_is_an_error(){
+        let conf = "
+            [carbon]
+            pattern = ^carbon\\.
+            retentions = 60s:1d
+            priority = high
+        ";
+        match StorageSchemas::parse(conf, default_retentions()) {
+            Err(err) => assert_eq!(err, ConfError::BadPriority("carbon".to_string(), "high".to_string())),
+            Ok(_) => panic!("expected a bad priority error")
+        }
+    }
+
+    #[test]
+    fn schema_for_builds_matching_schema(){
+        let conf = "
+            [carbon]
+            pattern = ^carbon\\.
+            retentions = 60s:90d, 1h:1y
+        ";
+        let schemas = StorageSchemas::parse(conf, default_retentions()).unwrap();
+
+        assert_eq!(schemas.schema_for("carbon.agents.cpu").retention_policies.len(), 2);
+        assert_eq!(schemas.schema_for("servers.web1.cpu").retention_policies.len(), 1);
+    }
+}
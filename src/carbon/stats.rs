@@ -0,0 +1,41 @@
+/*
+
+Operational counters for the carbon daemon. They only move when the
+crate is built with the `stats` feature, otherwise `incr` is a no-op
+and `snapshot()` stays at zero. How they get exposed is up to the caller.
+
+*/
+
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+// Lines that made it through parsing and onto the writer channel
+pub static POINTS_RECEIVED : AtomicUsize = AtomicUsize::new(0);
+// One per rejected line over both TCP and UDP, plus one per UDP datagram
+// that isn't valid UTF-8 (its lines can't be told apart)
+pub static PARSE_ERRORS : AtomicUsize = AtomicUsize::new(0);
+pub static POINTS_WRITTEN : AtomicUsize = AtomicUsize::new(0);
+pub static WRITE_ERRORS : AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub points_received: usize,
+    pub parse_errors: usize,
+    pub points_written: usize,
+    pub write_errors: usize
+}
+
+#[inline]
+pub fn incr(counter: &AtomicUsize) {
+    if cfg!(feature = "stats") {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn snapshot() -> Stats {
+    Stats {
+        points_received: POINTS_RECEIVED.load(Ordering::Relaxed),
+        parse_errors: PARSE_ERRORS.load(Ordering::Relaxed),
+        points_written: POINTS_WRITTEN.load(Ordering::Relaxed),
+        write_errors: WRITE_ERRORS.load(Ordering::Relaxed)
+    }
+}
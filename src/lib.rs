@@ -46,6 +46,7 @@ extern crate log;
 extern crate env_logger;
 
 extern crate regex;
+extern crate libc;
 
 extern crate whisper;
 